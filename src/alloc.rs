@@ -0,0 +1,78 @@
+//! A [`GlobalAlloc`] wrapper that tracks how many bytes are currently allocated.
+
+use std::alloc::{GlobalAlloc, Layout};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Wraps any [`GlobalAlloc`] and atomically tracks the number of bytes currently allocated,
+/// in the spirit of the `stats_alloc` crate.
+///
+/// Register it as the `#[global_allocator]` and hand
+/// [`TrackingAllocator::allocated`] to
+/// [`DurationsLayerBuilder::sample_memory`](crate::DurationsLayerBuilder::sample_memory)
+/// to get a memory-over-time track in the plot:
+///
+/// ```rust
+/// use std::alloc::System;
+/// use tracing_durations_export::alloc::TrackingAllocator;
+///
+/// #[global_allocator]
+/// static GLOBAL: TrackingAllocator<System> = TrackingAllocator::new(System);
+/// ```
+pub struct TrackingAllocator<A> {
+    inner: A,
+    allocated: AtomicUsize,
+}
+
+impl<A> TrackingAllocator<A> {
+    /// Wrap an existing allocator, e.g. [`std::alloc::System`].
+    pub const fn new(inner: A) -> Self {
+        Self {
+            inner,
+            allocated: AtomicUsize::new(0),
+        }
+    }
+
+    /// The number of bytes currently allocated through this allocator.
+    pub fn allocated(&self) -> usize {
+        self.allocated.load(Ordering::Relaxed)
+    }
+}
+
+// SAFETY: we only forward to the inner allocator and update a counter around it.
+unsafe impl<A: GlobalAlloc> GlobalAlloc for TrackingAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.inner.alloc(layout);
+        if !ptr.is_null() {
+            self.allocated.fetch_add(layout.size(), Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.inner.dealloc(ptr, layout);
+        self.allocated.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.inner.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            self.allocated.fetch_add(layout.size(), Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = self.inner.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            // Only the size delta changed, in either direction.
+            if new_size >= layout.size() {
+                self.allocated
+                    .fetch_add(new_size - layout.size(), Ordering::Relaxed);
+            } else {
+                self.allocated
+                    .fetch_sub(layout.size() - new_size, Ordering::Relaxed);
+            }
+        }
+        new_ptr
+    }
+}
@@ -1,9 +1,20 @@
 use anyhow::{Context, Result};
 use clap::Parser;
+use serde::Deserialize;
 use std::io::{BufRead, BufReader};
 use std::path::PathBuf;
 use std::time::Duration;
-use tracing_durations_export::plot::{plot, OwnedSpanInfo, PlotConfig, PlotLayout};
+use tracing_durations_export::plot::{
+    plot, plot_flamegraph, to_folded_stacks, MemorySample, OwnedSpanInfo, PlotConfig, PlotLayout,
+};
+
+/// A line in an exported durations file: either a span or a memory sample.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Record {
+    Span(OwnedSpanInfo),
+    Memory(MemorySample),
+}
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -35,6 +46,18 @@ struct Args {
     /// The color for the plots in the total region. Default: semi-transparent blue
     #[clap(long, default_value_t = PlotConfig::default().color_bottom)]
     color_bottom: String,
+    /// Render a memory-usage-over-time track at the top from the recorded samples
+    #[clap(long)]
+    memory_track: bool,
+    /// The color of the memory track's filled area graph. Default: semi-transparent purple
+    #[clap(long, default_value_t = PlotConfig::default().memory_color)]
+    memory_color: String,
+    /// Render an icicle/flamegraph from the `parents` call tree instead of the timeline
+    #[clap(long)]
+    flamegraph: bool,
+    /// Write folded stacks for the flamegraph/inferno toolchain to this file and exit
+    #[clap(long)]
+    folded: Option<PathBuf>,
 }
 
 fn main() -> Result<()> {
@@ -42,7 +65,7 @@ fn main() -> Result<()> {
 
     // Read input
     let reader = BufReader::new(fs::File::open(&args.input)?);
-    let spans: Vec<OwnedSpanInfo> = reader
+    let records: Vec<Record> = reader
         .lines()
         .map(|line| {
             let string = line.context("Failed to read line from input file")?;
@@ -50,6 +73,21 @@ fn main() -> Result<()> {
         })
         .collect::<Result<_>>()?;
 
+    let mut spans = Vec::new();
+    let mut memory = Vec::new();
+    for record in records {
+        match record {
+            Record::Span(span) => spans.push(span),
+            Record::Memory(sample) => memory.push(sample),
+        }
+    }
+
+    // The folded-stack exporter doesn't need the timeline extent.
+    if let Some(folded) = &args.folded {
+        fs::write(folded, to_folded_stacks(&spans)).context("Failed to write folded stacks")?;
+        return Ok(());
+    }
+
     let end = spans
         .iter()
         .map(|span| span.end)
@@ -64,9 +102,15 @@ fn main() -> Result<()> {
         color_top_blocking: args.color_top_blocking,
         color_top_threadpool: args.color_top_threadpool,
         color_bottom: args.color_bottom,
+        memory_track: args.memory_track,
+        memory_color: args.memory_color,
     };
 
-    let document = plot(&spans, end, &plot_config, &PlotLayout::default());
+    let document = if args.flamegraph {
+        plot_flamegraph(&spans, &plot_config, &PlotLayout::default())
+    } else {
+        plot(&spans, &memory, end, &plot_config, &PlotLayout::default())
+    };
 
     let svg = args.output.unwrap_or(args.input.with_extension("svg"));
     svg::save(svg, &document).context("Failed to write svg")?;
@@ -0,0 +1,420 @@
+//! Interactive terminal viewer for exported durations files.
+//!
+//! Large traces produce svgs that are too wide and dense to read in a browser; this
+//! renders the same span timeline in the terminal with horizontal scroll/zoom on the
+//! time axis, vertical scroll over the per-name lanes, a live name filter and a detail
+//! pane for the selected span. The lane packing is shared with the svg
+//! [`plot`](tracing_durations_export::plot::plot) via
+//! [`pack_lanes`](tracing_durations_export::plot::pack_lanes) so the layout matches.
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use ratatui::crossterm::ExecutableCommand;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::Terminal;
+use rustc_hash::FxHashMap;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::io::{self, BufRead, BufReader};
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing_durations_export::plot::{pack_lanes, OwnedSpanInfo};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    input: PathBuf,
+    /// Don't overlay bottom spans
+    #[clap(long)]
+    multi_lane: bool,
+    /// Remove spans shorter than this, in seconds
+    #[clap(long)]
+    min_length: Option<f32>,
+    /// Remove spans with this name
+    #[clap(long)]
+    remove: Option<Vec<String>>,
+}
+
+/// A line in an exported durations file; we only render the spans here.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Record {
+    Span(OwnedSpanInfo),
+    #[allow(dead_code)]
+    Other(serde_json::Value),
+}
+
+/// The lane layout derived from the spans, recomputed whenever the filter changes.
+struct Derived {
+    /// Merged full spans by id.
+    full_spans: FxHashMap<u64, OwnedSpanInfo>,
+    /// Span names in vertical order.
+    names: Vec<String>,
+    /// Lane index per span id.
+    span_lanes: FxHashMap<u64, usize>,
+    /// Number of lanes per name.
+    lane_counts: FxHashMap<String, usize>,
+}
+
+impl Derived {
+    /// Apply `remove`/`min_length` exactly like [`plot`](tracing_durations_export::plot::plot)
+    /// does before packing lanes.
+    fn new(
+        spans: &[OwnedSpanInfo],
+        remove: &HashSet<String>,
+        min_length: Option<Duration>,
+        multi_lane: bool,
+    ) -> Self {
+        let mut full_spans: FxHashMap<u64, OwnedSpanInfo> = FxHashMap::default();
+        for span in spans {
+            if remove.contains(&span.name) {
+                continue;
+            }
+            full_spans
+                .entry(span.id)
+                .or_insert_with(|| span.clone())
+                .end = span.end;
+        }
+        if let Some(min_length) = min_length {
+            full_spans.retain(|_id, span| span.end - span.start >= min_length);
+        }
+
+        // Order names by the first time they occur, matching the svg's left sidebar.
+        let mut earliest: Vec<(String, Duration)> = Vec::new();
+        let mut seen: FxHashMap<String, usize> = FxHashMap::default();
+        let mut ordered = full_spans.values().collect::<Vec<_>>();
+        ordered.sort_by_key(|span| span.start);
+        for span in ordered {
+            match seen.get(&span.name) {
+                Some(&idx) => {
+                    if span.start < earliest[idx].1 {
+                        earliest[idx].1 = span.start;
+                    }
+                }
+                None => {
+                    seen.insert(span.name.clone(), earliest.len());
+                    earliest.push((span.name.clone(), span.start));
+                }
+            }
+        }
+        earliest.sort_by_key(|(_name, start)| *start);
+        let names = earliest.into_iter().map(|(name, _)| name).collect();
+
+        let (span_lanes, lane_counts) = pack_lanes(&full_spans, multi_lane);
+        Self {
+            full_spans,
+            names,
+            span_lanes: span_lanes.into_iter().collect(),
+            lane_counts: lane_counts.into_iter().collect(),
+        }
+    }
+}
+
+/// The interactive viewer state.
+struct App {
+    spans: Vec<OwnedSpanInfo>,
+    end: Duration,
+    multi_lane: bool,
+    remove: HashSet<String>,
+    min_length: Option<Duration>,
+    /// Filter box buffer; on submit the name is added to `remove`, reusing the
+    /// svg's exact-name removal semantics.
+    filter: String,
+    editing_filter: bool,
+    /// Left edge of the visible time window.
+    view_start: Duration,
+    /// Width of the visible time window (smaller means zoomed in).
+    view_width: Duration,
+    /// Index of the selected name lane.
+    selected: usize,
+    derived: Derived,
+}
+
+impl App {
+    fn new(args: &Args, spans: Vec<OwnedSpanInfo>, end: Duration) -> Self {
+        let remove: HashSet<String> = args.remove.clone().unwrap_or_default().into_iter().collect();
+        let min_length = args.min_length.map(Duration::from_secs_f32);
+        let derived = Derived::new(&spans, &remove, min_length, args.multi_lane);
+        Self {
+            spans,
+            end,
+            multi_lane: args.multi_lane,
+            remove,
+            min_length,
+            filter: String::new(),
+            editing_filter: false,
+            view_start: Duration::ZERO,
+            view_width: end,
+            selected: 0,
+            derived,
+        }
+    }
+
+    fn rebuild(&mut self) {
+        self.derived = Derived::new(&self.spans, &self.remove, self.min_length, self.multi_lane);
+        self.selected = self.selected.min(self.visible_names().len().saturating_sub(1));
+    }
+
+    /// Names in vertical order; removal happens in [`Derived::new`] via `remove`.
+    fn visible_names(&self) -> Vec<&String> {
+        self.derived.names.iter().collect()
+    }
+
+    /// Add the filter box's name to `remove` and recompute the lanes.
+    fn apply_filter(&mut self) {
+        if !self.filter.is_empty() {
+            self.remove.insert(std::mem::take(&mut self.filter));
+            self.rebuild();
+        }
+    }
+
+    /// Vertical row offset that puts the selected lane at the top of the timeline,
+    /// so it's always scrolled into view. Text rows stack `lane_counts` per name.
+    fn selected_row_offset(&self) -> u16 {
+        self.visible_names()
+            .iter()
+            .take(self.selected)
+            .map(|name| self.derived.lane_counts.get(*name).copied().unwrap_or(1) as u16)
+            .sum()
+    }
+
+    fn zoom(&mut self, factor: f32) {
+        let width = (self.view_width.as_secs_f32() * factor).clamp(1e-6, self.end.as_secs_f32());
+        self.view_width = Duration::from_secs_f32(width);
+    }
+
+    fn scroll_time(&mut self, delta: f32) {
+        let start = (self.view_start.as_secs_f32() + self.view_width.as_secs_f32() * delta)
+            .clamp(0.0, (self.end.as_secs_f32() - self.view_width.as_secs_f32()).max(0.0));
+        self.view_start = Duration::from_secs_f32(start);
+    }
+
+    /// The span to show in the detail pane: the one in the selected lane nearest the
+    /// center of the current view.
+    fn selected_span(&self) -> Option<&OwnedSpanInfo> {
+        let name = self.visible_names().get(self.selected).copied()?;
+        let center = self.view_start + self.view_width / 2;
+        self.derived
+            .full_spans
+            .values()
+            .filter(|span| &span.name == name)
+            .min_by_key(|span| {
+                let mid = span.start + (span.end - span.start) / 2;
+                if mid > center {
+                    mid - center
+                } else {
+                    center - mid
+                }
+            })
+    }
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let reader = BufReader::new(fs::File::open(&args.input)?);
+    let mut spans = Vec::new();
+    for line in reader.lines() {
+        let line = line.context("Failed to read line from input file")?;
+        match serde_json::from_str(&line).context("Invalid line in input file")? {
+            Record::Span(span) => spans.push(span),
+            Record::Other(_) => {}
+        }
+    }
+    let end = spans
+        .iter()
+        .map(|span| span.end)
+        .max()
+        .context("Input file is empty")?;
+
+    let mut app = App::new(&args, spans, end);
+
+    enable_raw_mode()?;
+    io::stdout().execute(EnterAlternateScreen)?;
+    let backend = ratatui::backend::CrosstermBackend::new(io::stdout());
+    let mut terminal = Terminal::new(backend)?;
+
+    let result = run(&mut terminal, &mut app);
+
+    disable_raw_mode()?;
+    io::stdout().execute(LeaveAlternateScreen)?;
+    result
+}
+
+fn run<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()> {
+    loop {
+        terminal.draw(|frame| draw(frame, app))?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+
+        if app.editing_filter {
+            match key.code {
+                KeyCode::Enter => {
+                    app.apply_filter();
+                    app.editing_filter = false;
+                }
+                KeyCode::Esc => {
+                    app.filter.clear();
+                    app.editing_filter = false;
+                }
+                KeyCode::Backspace => {
+                    app.filter.pop();
+                }
+                KeyCode::Char(c) => app.filter.push(c),
+                _ => {}
+            }
+            continue;
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => break,
+            KeyCode::Char('/') => app.editing_filter = true,
+            KeyCode::Left => app.scroll_time(-0.1),
+            KeyCode::Right => app.scroll_time(0.1),
+            KeyCode::Up => app.selected = app.selected.saturating_sub(1),
+            KeyCode::Down => {
+                app.selected = (app.selected + 1).min(app.visible_names().len().saturating_sub(1));
+            }
+            KeyCode::Char('+') | KeyCode::Char('=') => app.zoom(0.5),
+            KeyCode::Char('-') => app.zoom(2.0),
+            // Adjust the live `min_length` filter.
+            KeyCode::Char('m') => {
+                let step = Duration::from_micros(100);
+                app.min_length = Some(app.min_length.unwrap_or(Duration::ZERO) + step);
+                app.rebuild();
+            }
+            KeyCode::Char('n') => {
+                app.min_length = app
+                    .min_length
+                    .and_then(|len| len.checked_sub(Duration::from_micros(100)))
+                    .filter(|len| !len.is_zero());
+                app.rebuild();
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn draw(frame: &mut ratatui::Frame, app: &App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Min(3),
+            Constraint::Length(7),
+        ])
+        .split(frame.area());
+
+    // Status / filter line.
+    let status = if app.editing_filter {
+        format!("remove span name: {}_", app.filter)
+    } else {
+        format!(
+            "q quit  ←/→ scroll  ↑/↓ select  +/- zoom  / remove ({} hidden)  m/n min-length ({})",
+            app.remove.len(),
+            app.min_length
+                .map(|len| format!("{:.6}s", len.as_secs_f32()))
+                .unwrap_or_else(|| "off".to_string()),
+        )
+    };
+    frame.render_widget(Paragraph::new(status), chunks[0]);
+
+    draw_timeline(frame, app, chunks[1]);
+    draw_detail(frame, app, chunks[2]);
+}
+
+fn draw_timeline(frame: &mut ratatui::Frame, app: &App, area: Rect) {
+    let block = Block::default().borders(Borders::ALL).title(format!(
+        "{:.6}s – {:.6}s",
+        app.view_start.as_secs_f32(),
+        (app.view_start + app.view_width).as_secs_f32()
+    ));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let names = app.visible_names();
+    let label_width = names
+        .iter()
+        .map(|name| name.len())
+        .max()
+        .unwrap_or(0)
+        .min(inner.width as usize / 3) as u16;
+    let bar_width = inner.width.saturating_sub(label_width + 1) as usize;
+    if bar_width == 0 {
+        return;
+    }
+
+    let view_start = app.view_start.as_secs_f32();
+    let view_width = app.view_width.as_secs_f32().max(1e-9);
+    let cell = |time: f32| (((time - view_start) / view_width) * bar_width as f32) as isize;
+
+    let mut lines = Vec::new();
+    for (idx, name) in names.iter().enumerate() {
+        let lanes = app.derived.lane_counts.get(*name).copied().unwrap_or(1);
+        // One text row per lane so overlapping spans stay readable, like the svg.
+        for lane in 0..lanes {
+            let mut row = vec![' '; bar_width];
+            for span in app.derived.full_spans.values() {
+                if &span.name != *name || app.derived.span_lanes.get(&span.id) != Some(&lane) {
+                    continue;
+                }
+                let start = cell(span.start.as_secs_f32()).max(0);
+                let stop = cell(span.end.as_secs_f32()).min(bar_width as isize);
+                for column in start..stop.max(start + 1) {
+                    if let Some(slot) = row.get_mut(column as usize) {
+                        *slot = '█';
+                    }
+                }
+            }
+            let label = if lane == 0 {
+                format!("{:>width$} ", name, width = label_width as usize)
+            } else {
+                format!("{:>width$} ", "", width = label_width as usize)
+            };
+            let style = if idx == app.selected {
+                Style::default().add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            lines.push(Line::from(vec![
+                Span::styled(label, style),
+                Span::raw(row.into_iter().collect::<String>()),
+            ]));
+        }
+    }
+
+    // Vertically scroll so the selected lane is in view, since large traces have far
+    // more lanes than fit on screen.
+    frame.render_widget(
+        Paragraph::new(lines).scroll((app.selected_row_offset(), 0)),
+        inner,
+    );
+}
+
+fn draw_detail(frame: &mut ratatui::Frame, app: &App, area: Rect) {
+    let block = Block::default().borders(Borders::ALL).title("details");
+    let text = if let Some(span) = app.selected_span() {
+        let duration = (span.end - span.start).as_secs_f32();
+        let mut lines = vec![Line::from(format!("{} {:.6}s", span.name, duration))];
+        for (key, value) in span.fields.iter().flatten() {
+            lines.push(Line::from(format!("{key}: {value}")));
+        }
+        lines
+    } else {
+        vec![Line::from("no span selected")]
+    };
+    frame.render_widget(Paragraph::new(text).block(block), area);
+}
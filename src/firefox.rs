@@ -0,0 +1,205 @@
+//! Export the spans as a Firefox Profiler "processed profile".
+//!
+//! The JSON produced here can be loaded directly into <https://profiler.firefox.com/>,
+//! which gives interactive zoom, range selection and name search that the static
+//! [`plot`](crate::plot) svg can't. Every span becomes an interval marker, either on
+//! the main thread or on a single thread that holds the spans of all other threads,
+//! so the marker chart mirrors the svg timeline.
+
+use std::collections::{BTreeSet, HashMap};
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use crate::plot::OwnedSpanInfo;
+
+/// Categories referenced by index from each marker's `category` column.
+///
+/// The order has to match the `meta.categories` table emitted in
+/// [`to_firefox_profile`]. Index 0 is the catch-all "Other" category.
+const CATEGORY_BLOCKING: usize = 1;
+const CATEGORY_THREADPOOL: usize = 2;
+
+/// Interns strings into a profile `stringArray`, returning the index of each.
+#[derive(Default)]
+struct StringArray {
+    strings: Vec<String>,
+    indices: HashMap<String, usize>,
+}
+
+impl StringArray {
+    fn intern(&mut self, string: &str) -> usize {
+        if let Some(index) = self.indices.get(string) {
+            return *index;
+        }
+        let index = self.strings.len();
+        self.strings.push(string.to_string());
+        self.indices.insert(string.to_string(), index);
+        index
+    }
+}
+
+/// Serialize the spans into the Firefox Profiler processed-profile format.
+///
+/// Mirrors [`plot`](crate::plot::plot)'s entry point: `spans` are the recorded
+/// active sections and `end` is the timestamp of the last span. Store the result
+/// with `serde_json::to_writer(file, &profile)`.
+pub fn to_firefox_profile(spans: &[OwnedSpanInfo], end: Duration) -> Value {
+    // All timestamps in the durations file are relative to the first span, so
+    // the profile's zero is `Duration::ZERO` and the interval is one millisecond.
+    let millis = |duration: Duration| duration.as_secs_f64() * 1000.0;
+
+    // One profile thread for the main thread and one for all other threads. We only
+    // know whether a span ran on the main thread, so that's the single identifier we
+    // split on.
+    let mut main_thread = ThreadBuilder::default();
+    let mut threadpool = ThreadBuilder::default();
+    for span in spans {
+        let thread = if span.is_main_thread {
+            &mut main_thread
+        } else {
+            &mut threadpool
+        };
+        let category = if span.is_main_thread {
+            CATEGORY_BLOCKING
+        } else {
+            CATEGORY_THREADPOOL
+        };
+        thread.push_marker(span, millis(span.start), millis(span.end), category);
+    }
+
+    let mut threads = Vec::new();
+    if !main_thread.is_empty() {
+        threads.push(main_thread.finish("Main Thread", true, 0));
+    }
+    if !threadpool.is_empty() {
+        threads.push(threadpool.finish("Threadpool", false, 1));
+    }
+
+    // The `Span` marker schema tells the profiler which keys of the marker payload
+    // to show (and make searchable) in the sidebar. The span fields are flattened
+    // into the payload, so every field key gets its own schema entry.
+    let field_keys: BTreeSet<&str> = spans
+        .iter()
+        .flat_map(|span| span.fields.iter().flatten().map(|(key, _)| key.as_str()))
+        .collect();
+    let mut span_schema_data = vec![json!({
+        "key": "name",
+        "label": "Name",
+        "format": "string",
+        "searchable": true,
+    })];
+    for key in &field_keys {
+        span_schema_data.push(json!({
+            "key": key,
+            "label": key,
+            "format": "string",
+            "searchable": true,
+        }));
+    }
+
+    json!({
+        "meta": {
+            "interval": 1,
+            "startTime": 0,
+            "profilingStartTime": 0,
+            "profilingEndTime": millis(end),
+            "product": "tracing-durations-export",
+            "version": 28,
+            "preprocessedProfileVersion": 50,
+            "processType": 0,
+            "symbolicated": true,
+            "markerSchema": [
+                {
+                    "name": "Span",
+                    "display": ["marker-chart", "marker-table"],
+                    "data": span_schema_data,
+                },
+            ],
+            "categories": [
+                { "name": "Other", "color": "grey", "subcategories": ["Other"] },
+                { "name": "Blocking", "color": "orange", "subcategories": ["Other"] },
+                { "name": "Threadpool", "color": "green", "subcategories": ["Other"] },
+            ],
+        },
+        "libs": [],
+        "threads": threads,
+    })
+}
+
+/// Accumulates the marker table of a single thread.
+#[derive(Default)]
+struct ThreadBuilder {
+    string_array: StringArray,
+    data: Vec<Value>,
+    name: Vec<usize>,
+    start_time: Vec<f64>,
+    end_time: Vec<f64>,
+    phase: Vec<u8>,
+    category: Vec<usize>,
+}
+
+impl ThreadBuilder {
+    fn is_empty(&self) -> bool {
+        self.name.is_empty()
+    }
+
+    fn push_marker(&mut self, span: &OwnedSpanInfo, start: f64, end: f64, category: usize) {
+        let name = self.string_array.intern(&span.name);
+        // Flatten the fields into the marker payload so the `Span` marker schema can
+        // surface each one in the tooltip sidebar.
+        let mut payload = serde_json::Map::new();
+        payload.insert("type".to_string(), Value::from("Span"));
+        payload.insert("name".to_string(), Value::from(span.name.clone()));
+        for (key, value) in span.fields.iter().flatten() {
+            payload.insert(key.clone(), Value::from(value.clone()));
+        }
+        self.data.push(Value::Object(payload));
+        self.name.push(name);
+        self.start_time.push(start);
+        self.end_time.push(end);
+        // Phase 1 is an interval marker (start plus end).
+        self.phase.push(1);
+        self.category.push(category);
+    }
+
+    fn finish(self, name: &str, is_main_thread: bool, tid: u64) -> Value {
+        let length = self.name.len();
+        json!({
+            "processType": "default",
+            "name": name,
+            "isMainThread": is_main_thread,
+            "pid": "0",
+            "tid": tid,
+            "markers": {
+                "data": self.data,
+                "name": self.name,
+                "startTime": self.start_time,
+                "endTime": self.end_time,
+                "phase": self.phase,
+                "category": self.category,
+                "length": length,
+            },
+            "samples": {
+                "stack": [],
+                "time": [],
+                "weight": null,
+                "weightType": "samples",
+                "length": 0,
+            },
+            "stackTable": { "frame": [], "prefix": [], "category": [], "subcategory": [], "length": 0 },
+            "frameTable": {
+                "address": [], "inlineDepth": [], "category": [], "subcategory": [],
+                "func": [], "nativeSymbol": [], "innerWindowID": [], "line": [], "column": [],
+                "length": 0,
+            },
+            "funcTable": {
+                "name": [], "isJS": [], "relevantForJS": [], "resource": [],
+                "fileName": [], "lineNumber": [], "columnNumber": [], "length": 0,
+            },
+            "resourceTable": { "lib": [], "name": [], "host": [], "type": [], "length": 0 },
+            "nativeSymbols": { "libIndex": [], "address": [], "name": [], "functionSize": [], "length": 0 },
+            "stringArray": self.string_array.strings,
+        })
+    }
+}
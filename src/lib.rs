@@ -56,6 +56,8 @@ use std::io::{BufWriter, Write};
 use std::marker::PhantomData;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
+#[cfg(feature = "plot")]
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::{Duration, Instant};
 use std::{io, iter};
 use tracing::field::Field;
@@ -64,6 +66,10 @@ use tracing_subscriber::layer::Context;
 use tracing_subscriber::registry::LookupSpan;
 use tracing_subscriber::Layer;
 
+#[cfg(feature = "plot")]
+pub mod alloc;
+#[cfg(feature = "plot")]
+pub mod firefox;
 #[cfg(feature = "plot")]
 pub mod plot;
 
@@ -98,6 +104,16 @@ pub struct DurationsLayerBuilder {
     plot_config: plot::PlotConfig,
     #[cfg(feature = "plot")]
     plot_layout: plot::PlotLayout,
+    /// See [`DurationsLayerBuilder::sample_memory`].
+    #[cfg(feature = "plot")]
+    memory_sampler: Option<MemorySampler>,
+}
+
+/// The configuration of the background memory sampler thread.
+#[cfg(feature = "plot")]
+struct MemorySampler {
+    interval: Duration,
+    source: fn() -> usize,
 }
 
 impl Default for DurationsLayerBuilder {
@@ -112,6 +128,8 @@ impl Default for DurationsLayerBuilder {
             plot_config: plot::PlotConfig::default(),
             #[cfg(feature = "plot")]
             plot_layout: plot::PlotLayout::default(),
+            #[cfg(feature = "plot")]
+            memory_sampler: None,
         }
     }
 }
@@ -132,6 +150,8 @@ impl DurationsLayerBuilder {
             #[cfg(feature = "plot")]
             plot_data: Arc::new(Mutex::default()),
             #[cfg(feature = "plot")]
+            memory_data: Arc::new(Mutex::default()),
+            #[cfg(feature = "plot")]
             plot_file: self.plot_file,
             with_fields: self.with_fields,
             with_parents: self.with_parents,
@@ -141,7 +161,51 @@ impl DurationsLayerBuilder {
             plot_layout: self.plot_layout,
             _inner: PhantomData,
         };
-        let guard = layer.drop_guard();
+        #[cfg_attr(not(feature = "plot"), allow(unused_mut))]
+        let mut guard = layer.drop_guard();
+
+        // Spawn the background memory sampler, writing `(timestamp, bytes_allocated)`
+        // records into the same export stream as the spans.
+        #[cfg(feature = "plot")]
+        if let Some(sampler) = self.memory_sampler {
+            let shutdown = Arc::new(AtomicBool::new(false));
+            let out = layer.out.clone();
+            let memory_data = layer.memory_data.clone();
+            let stop = shutdown.clone();
+            // Sample against the shared `START` clock so the memory track and the
+            // spans share a time origin and line up in the plot. This anchors `START`
+            // at subscriber-setup time rather than at the first span, which is the
+            // correct trade to keep the two series correlated.
+            std::thread::Builder::new()
+                .name("tracing-durations-memory-sampler".to_string())
+                .spawn(move || {
+                    while !stop.load(Ordering::Relaxed) {
+                        let sample = plot::MemorySample {
+                            timestamp: START.elapsed(),
+                            bytes_allocated: (sampler.source)() as u64,
+                        };
+                        let mut locked = out.lock().expect("There was a prior panic");
+                        // Check again under the lock, so nothing is written once the
+                        // drop guard has stopped the sampler and flushed.
+                        if stop.load(Ordering::Relaxed) {
+                            break;
+                        }
+                        if let Some(mut writer) = locked.as_mut() {
+                            serde_json::to_writer(&mut writer, &sample).unwrap();
+                            writeln!(&mut writer).unwrap();
+                        }
+                        drop(locked);
+                        memory_data
+                            .lock()
+                            .expect("There was a prior panic")
+                            .push(sample);
+                        std::thread::sleep(sampler.interval);
+                    }
+                })
+                .expect("Failed to spawn memory sampler thread");
+            guard.memory_shutdown = Some(shutdown);
+        }
+
         Ok((layer, guard))
     }
 
@@ -233,6 +297,22 @@ impl DurationsLayerBuilder {
             ..self
         }
     }
+
+    /// Sample the current heap usage every `interval` and record a memory-over-time
+    /// track in the plot.
+    ///
+    /// `source` returns the bytes currently allocated, typically
+    /// [`TrackingAllocator::allocated`](crate::alloc::TrackingAllocator::allocated)
+    /// of a `#[global_allocator]`. The samples are written into the durations file
+    /// alongside the spans and, with [`PlotConfig::memory_track`](plot::PlotConfig::memory_track)
+    /// enabled, rendered as a filled area graph at the top of the timeline.
+    #[cfg(feature = "plot")]
+    pub fn sample_memory(self, interval: Duration, source: fn() -> usize) -> Self {
+        Self {
+            memory_sampler: Some(MemorySampler { interval, source }),
+            ..self
+        }
+    }
 }
 
 type CollectedFields<RS> = HashMap<&'static str, String, RS>;
@@ -258,13 +338,24 @@ pub struct DurationsLayerDropGuard {
     #[cfg(feature = "plot")]
     plot_data: Arc<Mutex<Vec<plot::OwnedSpanInfo>>>,
     #[cfg(feature = "plot")]
+    memory_data: Arc<Mutex<Vec<plot::MemorySample>>>,
+    #[cfg(feature = "plot")]
     plot_config: plot::PlotConfig,
     #[cfg(feature = "plot")]
     plot_layout: plot::PlotLayout,
+    /// Set to stop the background memory sampler thread, if any.
+    #[cfg(feature = "plot")]
+    memory_shutdown: Option<Arc<AtomicBool>>,
 }
 
 impl Drop for DurationsLayerDropGuard {
     fn drop(&mut self) {
+        // Stop the memory sampler before flushing so no further records are written.
+        #[cfg(feature = "plot")]
+        if let Some(shutdown) = &self.memory_shutdown {
+            shutdown.store(true, Ordering::Relaxed);
+        }
+
         if let Some(out) = self.out.lock().expect("There was a prior panic").as_mut() {
             if let Err(err) = out.flush() {
                 eprintln!("`DurationLayer` failed to flush out file: {err}");
@@ -285,6 +376,7 @@ impl Drop for DurationsLayerDropGuard {
                 if let Some(end) = end {
                     let svg = plot::plot(
                         &self.plot_data.lock().expect("There was a prior panic"),
+                        &self.memory_data.lock().expect("There was a prior panic"),
                         end,
                         &self.plot_config,
                         &self.plot_layout,
@@ -313,6 +405,8 @@ pub struct DurationsLayer<S, RS = RandomState> {
     #[cfg(feature = "plot")]
     plot_data: Arc<Mutex<Vec<plot::OwnedSpanInfo>>>,
     #[cfg(feature = "plot")]
+    memory_data: Arc<Mutex<Vec<plot::MemorySample>>>,
+    #[cfg(feature = "plot")]
     plot_file: Option<PathBuf>,
     with_fields: bool,
     with_parents: bool,
@@ -332,9 +426,13 @@ impl<S> DurationsLayer<S> {
             #[cfg(feature = "plot")]
             plot_data: self.plot_data.clone(),
             #[cfg(feature = "plot")]
+            memory_data: self.memory_data.clone(),
+            #[cfg(feature = "plot")]
             plot_config: self.plot_config.clone(),
             #[cfg(feature = "plot")]
             plot_layout: self.plot_layout.clone(),
+            #[cfg(feature = "plot")]
+            memory_shutdown: None,
         }
     }
 }
@@ -6,9 +6,9 @@ use std::time::Duration;
 
 use itertools::Itertools;
 use rustc_hash::FxHashMap;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use svg::Document;
-use svg::node::element::{Rectangle, SVG, Text, Title};
+use svg::node::element::{Polygon, Rectangle, SVG, Text, Title};
 
 /// Owned type for deserialization.
 #[derive(Deserialize, Clone)]
@@ -17,7 +17,6 @@ pub struct OwnedSpanInfo {
     pub name: String,
     pub start: Duration,
     pub end: Duration,
-    #[allow(dead_code)]
     pub parents: Option<Vec<u64>>,
     pub is_main_thread: bool,
     pub fields: Option<HashMap<String, String>>,
@@ -29,6 +28,16 @@ impl OwnedSpanInfo {
     }
 }
 
+/// A heap-usage measurement recorded by the memory sampler, a sibling record of
+/// [`OwnedSpanInfo`] in the export stream.
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct MemorySample {
+    /// Timestamp of the sample, on the same clock as the span timestamps.
+    pub timestamp: Duration,
+    /// Bytes allocated at this point in time.
+    pub bytes_allocated: u64,
+}
+
 /// Common visualization options.
 #[derive(Debug, Clone)]
 pub struct PlotConfig {
@@ -49,6 +58,11 @@ pub struct PlotConfig {
     pub color_top_threadpool: String,
     /// The color for the plots in the total region. Default: semi-transparent blue
     pub color_bottom: String,
+    /// Render a memory-usage-over-time track at the top of the timeline from the
+    /// recorded [`MemorySample`]s.
+    pub memory_track: bool,
+    /// The color of the memory track's filled area graph. Default: semi-transparent purple
+    pub memory_color: String,
 }
 
 impl Default for PlotConfig {
@@ -62,6 +76,8 @@ impl Default for PlotConfig {
             color_top_blocking: "#E69F0088".to_string(),
             color_top_threadpool: "#009E7388".to_string(),
             color_bottom: "#56B4E988".to_string(),
+            memory_track: false,
+            memory_color: "#CC79A788".to_string(),
         }
     }
 }
@@ -87,6 +103,8 @@ pub struct PlotLayout {
     pub multi_lane_padding: usize,
     /// The padding between different kinds of spans.
     pub section_padding_height: usize,
+    /// The height of the memory track at the top, when `memory_track` is enabled.
+    pub memory_track_height: usize,
 }
 
 impl Default for PlotLayout {
@@ -101,6 +119,7 @@ impl Default for PlotLayout {
             bar_height: 20,
             multi_lane_padding: 1,
             section_padding_height: 10,
+            memory_track_height: 60,
         }
     }
 }
@@ -110,6 +129,7 @@ impl Default for PlotLayout {
 /// You can store the result with `svg::save(plot_file, &svg)`.
 pub fn plot(
     spans: &[OwnedSpanInfo],
+    memory: &[MemorySample],
     end: Duration,
     config: &PlotConfig,
     layout: &PlotLayout,
@@ -171,31 +191,7 @@ pub fn plot(
 
     // In expanded mode, we avoid overlaps in different lanes, so we track
     // until which timestamp each lane is blocked and how many lanes we need.
-    let mut lanes_end: HashMap<&str, Vec<Duration>> = HashMap::new();
-    let mut span_lanes = HashMap::new();
-    let mut full_spans_sorted: Vec<_> = full_spans.values().collect();
-    full_spans_sorted.sort_by_key(|span| span.start);
-    for full_span in full_spans_sorted {
-        if config.multi_lane {
-            let lanes = lanes_end.entry(&full_span.name).or_default();
-            if let Some((idx, lane_end)) = lanes
-                .iter_mut()
-                .enumerate()
-                .find(|(_idx, end)| &full_span.start > end)
-            {
-                span_lanes.insert(full_span.id, idx);
-                *lane_end = full_span.end;
-            } else {
-                span_lanes.insert(full_span.id, lanes.len());
-                lanes.push(full_span.end)
-            }
-        } else {
-            span_lanes.insert(full_span.id, 0);
-            lanes_end
-                .entry(&full_span.name)
-                .or_insert_with(|| vec![full_span.end])[0] = full_span.end;
-        }
-    }
+    let (span_lanes, lane_counts) = pack_lanes(&full_spans, config.multi_lane);
 
     let extra_lane_height = layout.bar_height / 2 + layout.multi_lane_padding;
 
@@ -213,15 +209,24 @@ pub fn plot(
     let mut extra_lanes_cumulative = HashMap::new();
     for (name, _start) in earliest_starts {
         extra_lanes_cumulative.insert(name, extra_lanes_cur);
-        extra_lanes_cur += lanes_end[name].len() - 1;
+        extra_lanes_cur += lane_counts[name] - 1;
     }
 
+    // A memory track, if enabled and populated, is drawn above everything else and
+    // shifts the rest of the plot down by this much.
+    let memory_height = if config.memory_track && !memory.is_empty() {
+        layout.memory_track_height + layout.section_padding_height
+    } else {
+        0
+    };
+
     let total_width = layout.padding_left
         + layout.text_col_width
         + layout.content_col_width
         + layout.padding_right;
     // Don't forget the timeline row
     let total_height = layout.padding_top
+        + memory_height
         + (layout.bar_height + layout.section_padding_height) * (name_offsets.len() + 1)
         + extra_lane_height * extra_lanes_cur
         + layout.padding_bottom;
@@ -235,14 +240,14 @@ pub fn plot(
         .add(
             Text::new("0s")
                 .set("x", layout.text_col_width)
-                .set("y", layout.padding_top + layout.bar_height / 2)
+                .set("y", layout.padding_top + memory_height + layout.bar_height / 2)
                 .set("dominant-baseline", "middle")
                 .set("text-anchor", "start"),
         )
         .add(
             Text::new(format!("{:.3}s", end.as_secs_f32()))
                 .set("x", layout.text_col_width + layout.content_col_width)
-                .set("y", layout.padding_top + layout.bar_height / 2)
+                .set("y", layout.padding_top + memory_height + layout.bar_height / 2)
                 .set("dominant-baseline", "middle")
                 .set("text-anchor", "end"),
         );
@@ -256,12 +261,61 @@ pub fn plot(
         document = document.add(
             Text::new(text)
                 .set("x", layout.padding_left)
-                .set("y", layout.padding_top + layout.bar_height / 2)
+                .set("y", layout.padding_top + memory_height + layout.bar_height / 2)
                 .set("dominant-baseline", "middle")
                 .set("text-anchor", "start"),
         );
     }
 
+    // Draw the memory-usage-over-time track above everything else
+    if memory_height > 0 {
+        let peak = memory.iter().map(|sample| sample.bytes_allocated).max().unwrap_or(0);
+        let top = layout.padding_top as f32;
+        let baseline = (layout.padding_top + layout.memory_track_height) as f32;
+        let x_of = |timestamp: Duration| {
+            layout.text_col_width as f32
+                + layout.content_col_width as f32 * timestamp.as_secs_f32() / end.as_secs_f32()
+        };
+        let y_of = |bytes: u64| {
+            if peak == 0 {
+                baseline
+            } else {
+                baseline - (bytes as f32 / peak as f32) * layout.memory_track_height as f32
+            }
+        };
+
+        let mut samples = memory.to_vec();
+        samples.sort_by_key(|sample| sample.timestamp);
+        if let (Some(first), Some(last)) = (samples.first(), samples.last()) {
+            // A filled area from the baseline up to the sampled bytes at each timestamp.
+            let mut points = Vec::with_capacity(samples.len() + 2);
+            points.push(format!("{},{}", x_of(first.timestamp), baseline));
+            for sample in &samples {
+                points.push(format!(
+                    "{},{}",
+                    x_of(sample.timestamp),
+                    y_of(sample.bytes_allocated)
+                ));
+            }
+            points.push(format!("{},{}", x_of(last.timestamp), baseline));
+            document = document.add(
+                Polygon::new()
+                    .set("points", points.join(" "))
+                    .set("fill", config.memory_color.clone()),
+            );
+        }
+
+        // Label the y-axis with the peak usage.
+        document = document.add(
+            Text::new(format!("{peak} bytes"))
+                .set("x", layout.padding_left)
+                .set("y", top)
+                .set("font-size", "0.7em")
+                .set("dominant-baseline", "hanging")
+                .set("text-anchor", "start"),
+        );
+    }
+
     // Draw the legend on the left
     for (name, offset) in &name_offsets {
         document = document.add(
@@ -270,6 +324,7 @@ pub fn plot(
                 .set(
                     "y",
                     layout.padding_top
+                        + memory_height
                         + layout.bar_height / 2
                         + offset * (layout.bar_height + layout.section_padding_height)
                         + extra_lane_height * extra_lanes_cumulative[name],
@@ -306,7 +361,8 @@ pub fn plot(
                 )
                 .set(
                     "y",
-                    offset * (layout.bar_height + layout.section_padding_height)
+                    memory_height
+                        + offset * (layout.bar_height + layout.section_padding_height)
                         + extra_lane_height * extra_lanes_cumulative[span.name.as_str()],
                 )
                 .set(
@@ -324,8 +380,9 @@ pub fn plot(
     for full_span in full_spans.values() {
         let x = layout.text_col_width as f32
             + layout.content_col_width as f32 * full_span.start.as_secs_f32() / end.as_secs_f32();
-        let y = name_offsets[full_span.name.as_str()]
-            * (layout.bar_height + layout.section_padding_height)
+        let y = memory_height
+            + name_offsets[full_span.name.as_str()]
+                * (layout.bar_height + layout.section_padding_height)
             + extra_lane_height * extra_lanes_cumulative[full_span.name.as_str()]
             + extra_lane_height * span_lanes[&full_span.id]
             + layout.bar_height / 2;
@@ -364,3 +421,239 @@ pub fn plot(
     }
     document
 }
+
+/// A node in the call tree reconstructed from the `parents` edges.
+struct FlameNode {
+    name: String,
+    /// Summed active intervals of all entries of this span id (total time).
+    value: Duration,
+    fields: Option<HashMap<String, String>>,
+    children: Vec<u64>,
+}
+
+/// Reconstruct the call tree from the `parents` field.
+///
+/// Entries of the same span id have their active intervals summed, and a span with
+/// multiple parents is attributed to its first (immediate) parent. Returns the nodes
+/// keyed by span id and the ids of the roots.
+fn call_tree(spans: &[OwnedSpanInfo]) -> (FxHashMap<u64, FlameNode>, Vec<u64>) {
+    let mut nodes: FxHashMap<u64, FlameNode> = FxHashMap::default();
+    let mut first_parent: FxHashMap<u64, Option<u64>> = FxHashMap::default();
+    for span in spans {
+        let node = nodes.entry(span.id).or_insert_with(|| FlameNode {
+            name: span.name.clone(),
+            value: Duration::ZERO,
+            fields: span.fields.clone(),
+            children: Vec::new(),
+        });
+        node.value += span.end - span.start;
+        // The first entry of an id fixes its parent.
+        first_parent.entry(span.id).or_insert_with(|| {
+            span.parents
+                .as_ref()
+                .and_then(|parents| parents.first().copied())
+        });
+    }
+
+    let mut roots = Vec::new();
+    let ids: Vec<u64> = nodes.keys().copied().collect();
+    for id in ids {
+        match first_parent[&id] {
+            Some(parent) if nodes.contains_key(&parent) => {
+                nodes.get_mut(&parent).expect("parent present").children.push(id);
+            }
+            // No parent, or a parent that wasn't recorded: treat as a root.
+            _ => roots.push(id),
+        }
+    }
+    roots.sort_unstable();
+    for node in nodes.values_mut() {
+        node.children.sort_unstable();
+    }
+    (nodes, roots)
+}
+
+/// Export the call tree as folded stacks, one `name1;name2;name3 <microseconds>` line
+/// per frame with its self-time, compatible with the flamegraph/inferno toolchain.
+/// Frames with less than one microsecond of self-time get no line.
+pub fn to_folded_stacks(spans: &[OwnedSpanInfo]) -> String {
+    fn fold(
+        nodes: &FxHashMap<u64, FlameNode>,
+        id: u64,
+        stack: &mut Vec<String>,
+        visited: &mut HashSet<u64>,
+        out: &mut String,
+    ) {
+        // The `parents` field is untrusted on-disk data; a cycle would recurse forever.
+        if !visited.insert(id) {
+            return;
+        }
+        let node = &nodes[&id];
+        stack.push(node.name.clone());
+        let children_total: Duration = node.children.iter().map(|child| nodes[child].value).sum();
+        // Self-time is the frame's total minus what its children accounted for.
+        let self_time = node.value.checked_sub(children_total).unwrap_or(Duration::ZERO);
+        let micros = self_time.as_micros();
+        if micros > 0 {
+            out.push_str(&stack.join(";"));
+            out.push_str(&format!(" {micros}\n"));
+        }
+        for child in &node.children {
+            fold(nodes, *child, stack, visited, out);
+        }
+        stack.pop();
+        visited.remove(&id);
+    }
+
+    let (nodes, roots) = call_tree(spans);
+    let mut out = String::new();
+    let mut stack = Vec::new();
+    let mut visited = HashSet::new();
+    for root in roots {
+        fold(&nodes, root, &mut stack, &mut visited, &mut out);
+    }
+    out
+}
+
+/// Render the call tree reconstructed from the `parents` edges as an icicle graph.
+///
+/// Unlike [`plot`], which draws one lane per span name, this stacks each frame beneath
+/// its parent with a width proportional to the frame's total duration.
+pub fn plot_flamegraph(spans: &[OwnedSpanInfo], config: &PlotConfig, layout: &PlotLayout) -> SVG {
+    /// Lay the tree out left to right, children scaled to fit within their parent.
+    fn place(
+        nodes: &FxHashMap<u64, FlameNode>,
+        id: u64,
+        x: f32,
+        width: f32,
+        depth: usize,
+        visited: &mut HashSet<u64>,
+        out: &mut Vec<(u64, f32, f32, usize)>,
+    ) {
+        // The `parents` field is untrusted on-disk data; a cycle would recurse forever.
+        if !visited.insert(id) {
+            return;
+        }
+        out.push((id, x, width, depth));
+        let node = &nodes[&id];
+        let children_total: Duration = node.children.iter().map(|child| nodes[child].value).sum();
+        // Keep children within the parent: if they'd overflow, scale them down.
+        let denom = children_total.max(node.value).as_secs_f32();
+        if denom <= 0.0 {
+            visited.remove(&id);
+            return;
+        }
+        let mut cursor = x;
+        for child in &node.children {
+            let child_width = width * nodes[child].value.as_secs_f32() / denom;
+            place(nodes, *child, cursor, child_width, depth + 1, visited, out);
+            cursor += child_width;
+        }
+        visited.remove(&id);
+    }
+
+    let (nodes, roots) = call_tree(spans);
+    let total: Duration = roots.iter().map(|id| nodes[id].value).sum();
+
+    let content_left = (layout.padding_left + layout.text_col_width) as f32;
+    let content_width = layout.content_col_width as f32;
+    let mut placed = Vec::new();
+    if !total.is_zero() {
+        let mut cursor = content_left;
+        let mut visited = HashSet::new();
+        for root in &roots {
+            let width = content_width * nodes[root].value.as_secs_f32() / total.as_secs_f32();
+            place(&nodes, *root, cursor, width, 0, &mut visited, &mut placed);
+            cursor += width;
+        }
+    }
+
+    let max_depth = placed.iter().map(|(_, _, _, depth)| *depth).max().unwrap_or(0);
+    let total_width =
+        layout.padding_left + layout.text_col_width + layout.content_col_width + layout.padding_right;
+    let total_height = layout.padding_top + layout.bar_height * (max_depth + 1) + layout.padding_bottom;
+
+    let mut document = Document::new()
+        .set("width", total_width)
+        .set("height", total_height)
+        .set("viewBox", (0, 0, total_width, total_height));
+
+    for (id, x, width, depth) in placed {
+        let node = &nodes[&id];
+        let y = layout.padding_top + depth * layout.bar_height;
+        let tooltip = {
+            let fields = node
+                .fields
+                .iter()
+                .flatten()
+                .map(|(key, value)| format!("{key}: {value}"))
+                .join("\n");
+            format!("{} {:.3}s\n{}", node.name, node.value.as_secs_f32(), fields)
+        };
+        document = document.add(
+            Rectangle::new()
+                .set("x", x)
+                .set("y", y)
+                .set("width", width)
+                .set("height", layout.bar_height)
+                .set("fill", config.color_top_blocking.clone())
+                .set("stroke", "white")
+                .set("stroke-width", 1)
+                .add(Title::new(tooltip)),
+        );
+        // Only label frames wide enough to fit some text.
+        if width > 30.0 {
+            document = document.add(
+                Text::new(node.name.clone())
+                    .set("x", x + 2.0)
+                    .set("y", y + layout.bar_height / 2)
+                    .set("font-size", "0.7em")
+                    .set("dominant-baseline", "middle")
+                    .set("text-anchor", "start"),
+            );
+        }
+    }
+    document
+}
+
+/// Assign each span to a lane so that spans with the same name don't overlap in
+/// multi-lane mode.
+///
+/// Returns the lane index per span id and the number of lanes used per name. The
+/// svg [`plot`] and the terminal viewer share this so both lay out identically.
+pub fn pack_lanes(
+    full_spans: &FxHashMap<u64, OwnedSpanInfo>,
+    multi_lane: bool,
+) -> (HashMap<u64, usize>, HashMap<String, usize>) {
+    // We track until which timestamp each lane is blocked and how many lanes we need.
+    let mut lanes_end: HashMap<String, Vec<Duration>> = HashMap::new();
+    let mut span_lanes = HashMap::new();
+    let mut full_spans_sorted: Vec<_> = full_spans.values().collect();
+    full_spans_sorted.sort_by_key(|span| span.start);
+    for full_span in full_spans_sorted {
+        if multi_lane {
+            let lanes = lanes_end.entry(full_span.name.clone()).or_default();
+            if let Some((idx, lane_end)) = lanes
+                .iter_mut()
+                .enumerate()
+                .find(|(_idx, end)| &full_span.start > end)
+            {
+                span_lanes.insert(full_span.id, idx);
+                *lane_end = full_span.end;
+            } else {
+                span_lanes.insert(full_span.id, lanes.len());
+                lanes.push(full_span.end)
+            }
+        } else {
+            span_lanes.insert(full_span.id, 0);
+            lanes_end
+                .entry(full_span.name.clone())
+                .or_insert_with(|| vec![full_span.end])[0] = full_span.end;
+        }
+    }
+    let lane_counts = lanes_end
+        .into_iter()
+        .map(|(name, lanes)| (name, lanes.len()))
+        .collect();
+    (span_lanes, lane_counts)
+}